@@ -0,0 +1,274 @@
+//! Отбраковка сгенерированных миров по заданным критериям, по образцу
+//! повторной генерации миров в Dwarf Fortress, если они не проходят выбранные
+//! пользователем условия (например, "не меньше 40% суши и есть горный пояс").
+
+use std::fmt;
+
+use super::World;
+
+/// Критерии, которым должен соответствовать сгенерированный мир.
+/// Доли измеряются на грубой сетке широта/долгота, покрывающей всю планету.
+#[derive(Debug, Clone)]
+pub struct WorldConstraints {
+    min_land_fraction: f64,
+    max_land_fraction: f64,
+    min_mountainous_fraction: f64,
+    min_landmass_fraction: f64,
+    resolution: (usize, usize),
+}
+
+impl Default for WorldConstraints {
+    fn default() -> Self {
+        Self {
+            min_land_fraction: 0.0,
+            max_land_fraction: 1.0,
+            min_mountainous_fraction: 0.0,
+            min_landmass_fraction: 0.0,
+            resolution: (90, 180),
+        }
+    }
+}
+
+impl WorldConstraints {
+    /// Создаёт набор критериев без ограничений — любой сгенерированный мир
+    /// будет принят.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Задаёт минимальную долю суши (0.0..=1.0) относительно уровня моря.
+    pub fn with_min_land_fraction(mut self, value: f64) -> Self {
+        self.min_land_fraction = value;
+        self
+    }
+
+    /// Задаёт максимальную долю суши (0.0..=1.0) относительно уровня моря.
+    pub fn with_max_land_fraction(mut self, value: f64) -> Self {
+        self.max_land_fraction = value;
+        self
+    }
+
+    /// Задаёт минимальную долю гористой местности (0.0..=1.0).
+    pub fn with_min_mountainous_fraction(mut self, value: f64) -> Self {
+        self.min_mountainous_fraction = value;
+        self
+    }
+
+    /// Задаёт минимальную долю (относительно площади сетки), которую должен
+    /// занимать хотя бы один связный массив суши.
+    pub fn with_min_landmass_fraction(mut self, value: f64) -> Self {
+        self.min_landmass_fraction = value;
+        self
+    }
+
+    /// Задаёт разрешение сетки сэмплирования (число шагов по широте и
+    /// долготе), на которой измеряются критерии.
+    pub fn with_resolution(mut self, lat_steps: usize, lon_steps: usize) -> Self {
+        self.resolution = (lat_steps, lon_steps);
+        self
+    }
+
+    pub(super) fn resolution(&self) -> (usize, usize) {
+        self.resolution
+    }
+
+    pub(super) fn is_satisfied_by(&self, stats: &WorldStats) -> bool {
+        stats.land_fraction >= self.min_land_fraction
+            && stats.land_fraction <= self.max_land_fraction
+            && stats.mountainous_fraction >= self.min_mountainous_fraction
+            && stats.largest_landmass_fraction >= self.min_landmass_fraction
+    }
+}
+
+/// Измеренные статистики мира на сетке сэмплирования, используемые для
+/// проверки [`WorldConstraints`] и для диагностики неудачных попыток.
+#[derive(Debug, Clone, Copy)]
+pub struct WorldStats {
+    pub land_fraction: f64,
+    pub mountainous_fraction: f64,
+    pub largest_landmass_fraction: f64,
+}
+
+/// Высота рельефа над уровнем моря, начиная с которой местность считается
+/// гористой при измерении статистик.
+const MOUNTAINOUS_BAND: f64 = 0.35;
+
+pub(super) fn measure(world: &World, resolution: (usize, usize)) -> WorldStats {
+    let (lat_steps, lon_steps) = resolution;
+    let mut land = vec![false; lat_steps * lon_steps];
+    let mut mountainous = 0usize;
+
+    for i in 0..lat_steps {
+        let lat = -90.0 + 180.0 * (i as f64 + 0.5) / lat_steps as f64;
+
+        for j in 0..lon_steps {
+            let lon = -180.0 + 360.0 * (j as f64 + 0.5) / lon_steps as f64;
+            let elevation = world.elevation(lat, lon);
+            let is_land = elevation > world.sea_level;
+
+            land[i * lon_steps + j] = is_land;
+            if is_land && elevation > world.sea_level + MOUNTAINOUS_BAND {
+                mountainous += 1;
+            }
+        }
+    }
+
+    let total = (lat_steps * lon_steps) as f64;
+    let land_count = land.iter().filter(|&&is_land| is_land).count();
+    let largest_landmass = largest_connected_component(&land, lat_steps, lon_steps);
+
+    WorldStats {
+        land_fraction: land_count as f64 / total,
+        mountainous_fraction: mountainous as f64 / total,
+        largest_landmass_fraction: largest_landmass as f64 / total,
+    }
+}
+
+/// Находит размер крупнейшего связного массива суши на сетке, считая
+/// долготу замкнутой в кольцо (планета — сфера без шва по долготе).
+fn largest_connected_component(land: &[bool], lat_steps: usize, lon_steps: usize) -> usize {
+    let mut visited = vec![false; land.len()];
+    let mut largest = 0;
+
+    for start in 0..land.len() {
+        if !land[start] || visited[start] {
+            continue;
+        }
+
+        let mut size = 0;
+        let mut stack = vec![start];
+        visited[start] = true;
+
+        while let Some(idx) = stack.pop() {
+            size += 1;
+            let row = idx / lon_steps;
+            let col = idx % lon_steps;
+
+            for (dr, dc) in [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)] {
+                let nr = row as i32 + dr;
+                if nr < 0 || nr >= lat_steps as i32 {
+                    continue;
+                }
+
+                let nc = (col as i32 + dc).rem_euclid(lon_steps as i32) as usize;
+                let neighbor = nr as usize * lon_steps + nc;
+
+                if land[neighbor] && !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        largest = largest.max(size);
+    }
+
+    largest
+}
+
+/// Ошибка: ни одна из `max_attempts` попыток не удовлетворила критериям.
+/// Содержит статистики последней попытки, чтобы пользователь мог решить,
+/// какие критерии стоит ослабить.
+#[derive(Debug)]
+pub struct GenerationError {
+    pub attempts: u32,
+    pub stats: WorldStats,
+}
+
+impl fmt::Display for GenerationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "не удалось подобрать мир под заданные критерии за {} попыток; \
+             последняя попытка: доля суши {:.2}, доля гор {:.2}, крупнейший массив суши {:.2} \
+             от площади сетки — попробуйте ослабить критерии",
+            self.attempts,
+            self.stats.land_fraction,
+            self.stats.mountainous_fraction,
+            self.stats.largest_landmass_fraction
+        )
+    }
+}
+
+impl std::error::Error for GenerationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::super::WorldBuilder;
+    use super::*;
+
+    #[test]
+    fn largest_connected_component_is_zero_without_land() {
+        let land = [false; 12];
+        assert_eq!(largest_connected_component(&land, 3, 4), 0);
+    }
+
+    #[test]
+    fn largest_connected_component_counts_a_single_cell() {
+        let mut land = [false; 12];
+        land[5] = true;
+        assert_eq!(largest_connected_component(&land, 3, 4), 1);
+    }
+
+    #[test]
+    fn largest_connected_component_ignores_disconnected_islands() {
+        // 3x4 сетка, два несоседних острова по одной клетке.
+        let mut land = [false; 12];
+        land[0] = true;
+        land[11] = true;
+        assert_eq!(largest_connected_component(&land, 3, 4), 1);
+    }
+
+    #[test]
+    fn largest_connected_component_merges_across_the_longitude_seam() {
+        // Первая и последняя колонки одной строки соседствуют, так как
+        // долгота замкнута в кольцо.
+        let lon_steps = 4;
+        let mut land = vec![false; 2 * lon_steps];
+        land[0] = true;
+        land[lon_steps - 1] = true;
+
+        assert_eq!(largest_connected_component(&land, 2, lon_steps), 2);
+    }
+
+    #[test]
+    fn largest_connected_component_covers_a_fully_connected_grid() {
+        let land = [true; 12];
+        assert_eq!(largest_connected_component(&land, 3, 4), 12);
+    }
+
+    #[test]
+    fn unconstrained_constraints_accept_any_stats() {
+        let stats = WorldStats {
+            land_fraction: 0.01,
+            mountainous_fraction: 0.0,
+            largest_landmass_fraction: 0.001,
+        };
+
+        assert!(WorldConstraints::new().is_satisfied_by(&stats));
+    }
+
+    #[test]
+    fn constraints_reject_stats_outside_the_requested_bounds() {
+        let stats = WorldStats {
+            land_fraction: 0.1,
+            mountainous_fraction: 0.0,
+            largest_landmass_fraction: 0.05,
+        };
+
+        let constraints = WorldConstraints::new().with_min_land_fraction(0.4);
+
+        assert!(!constraints.is_satisfied_by(&stats));
+    }
+
+    #[test]
+    fn measure_produces_fractions_within_unit_range() {
+        let world = WorldBuilder::new().set_seed(9001).build();
+        let stats = measure(&world, (20, 40));
+
+        assert!((0.0..=1.0).contains(&stats.land_fraction));
+        assert!((0.0..=1.0).contains(&stats.mountainous_fraction));
+        assert!((0.0..=1.0).contains(&stats.largest_landmass_fraction));
+        assert!(stats.largest_landmass_fraction <= stats.land_fraction + f64::EPSILON);
+    }
+}
@@ -0,0 +1,292 @@
+//! Генерация континентальной структуры планеты.
+//!
+//! Графа шума строится поверх параметров конкретного [`super::WorldBuilder`],
+//! а не глобальных статических переменных, так что несколько миров в одном
+//! процессе могут иметь разную форму континентов, гор и холмов.
+//!
+//! Граф собран по образцу классического "complex planet" из libnoise:
+//! базовые континенты задают крупномасштабную форму суши, а горы, холмы,
+//! равнины и бесплодные земли примешиваются поверх неё в зависимости от
+//! отдельного низкочастотного шума "типа местности".
+
+use noise::{
+    Add, Billow, Clamp, Curve, Fbm, MultiFractal, NoiseFn, Perlin, RidgedMulti, ScaleBias, Seedable,
+    Select, Turbulence,
+};
+
+use super::preset::WorldParams;
+
+const CONTINENT_SEED_OFFSET: u32 = 0;
+const MOUNTAIN_SEED_OFFSET: u32 = 100;
+const HILLS_SEED_OFFSET: u32 = 200;
+const PLAINS_SEED_OFFSET: u32 = 300;
+const BADLANDS_SEED_OFFSET: u32 = 400;
+const TERRAIN_TYPE_SEED_OFFSET: u32 = 500;
+
+/// Собирает граф модулей шума, формирующий итоговую высоту рельефа планеты,
+/// по параметрам конкретного мира.
+pub(super) fn build_elevation(
+    seed: u32,
+    params: &WorldParams,
+) -> Box<dyn NoiseFn<[f64; 3]> + Send + Sync> {
+    // 1: базовое определение континентов — fBm, сглаженный кривой у уровня
+    // моря и поднимающийся круче над ним.
+    let continent_fbm = Fbm::new()
+        .set_seed(seed.wrapping_add(CONTINENT_SEED_OFFSET))
+        .set_frequency(params.continent_frequency)
+        .set_persistence(0.5)
+        .set_lacunarity(params.continent_lacunarity)
+        .set_octaves(14);
+
+    // Контрольные точки сдвинуты на SEA_LEVEL, чтобы перегиб кривой (резкий
+    // подъём от подводного склона к суше) происходил ровно на уровне моря,
+    // а не был жёстко прибит к нулю.
+    let sl = params.sea_level;
+    let continent_curve = Curve::new(continent_fbm)
+        .add_control_point(-2.0000 + sl, -1.625)
+        .add_control_point(-1.0000 + sl, -1.375)
+        .add_control_point(0.0000 + sl, -0.375)
+        .add_control_point(0.0625 + sl, 0.125)
+        .add_control_point(0.1250 + sl, 0.250)
+        .add_control_point(0.2500 + sl, 1.000)
+        .add_control_point(0.5000 + sl, 0.250)
+        .add_control_point(0.7500 + sl, 0.250)
+        .add_control_point(1.0000 + sl, 0.500)
+        .add_control_point(2.0000 + sl, 0.500);
+
+    // 2: вырезаем континентальные шельфы, обрезая значения ниже SHELF_LEVEL.
+    let continent_def = Clamp::new(continent_curve).set_bounds(params.shelf_level, 1.0);
+
+    // 3: горы — ridged multifractal, деформированный турбулентностью на
+    // MOUNTAINS_TWIST, приподнятый к пикам и приглушённый в долинах на
+    // MOUNTAIN_GLACIATION.
+    let mountains_base = RidgedMulti::new()
+        .set_seed(seed.wrapping_add(MOUNTAIN_SEED_OFFSET))
+        .set_frequency(1813.0)
+        .set_lacunarity(params.mountain_lacunarity)
+        .set_octaves(8);
+
+    let mountains_warped = Turbulence::new(mountains_base)
+        .set_seed(seed.wrapping_add(MOUNTAIN_SEED_OFFSET + 1))
+        .set_frequency(1813.0 * 2.0)
+        .set_power(params.mountains_twist / 1813.0)
+        .set_roughness(6);
+
+    let mountains = ScaleBias::new(mountains_warped)
+        .set_scale(1.0 - params.mountain_glaciation)
+        .set_bias(params.mountain_glaciation);
+
+    // 4: холмы — billow noise, деформированный турбулентностью на HILLS_TWIST.
+    let hills_base = Billow::new()
+        .set_seed(seed.wrapping_add(HILLS_SEED_OFFSET))
+        .set_frequency(1663.0)
+        .set_persistence(0.5)
+        .set_lacunarity(params.hills_lacunarity)
+        .set_octaves(6);
+
+    let hills_warped = Turbulence::new(hills_base)
+        .set_seed(seed.wrapping_add(HILLS_SEED_OFFSET + 1))
+        .set_frequency(1663.0 * 2.0)
+        .set_power(params.hills_twist / 1663.0)
+        .set_roughness(4);
+
+    let hills = ScaleBias::new(hills_warped).set_scale(0.5).set_bias(0.5);
+
+    // 5: равнины — billow noise низкой амплитуды.
+    let plains_base = Billow::new()
+        .set_seed(seed.wrapping_add(PLAINS_SEED_OFFSET))
+        .set_frequency(1129.0)
+        .set_persistence(0.5)
+        .set_lacunarity(params.plains_lacunarity)
+        .set_octaves(8);
+
+    let plains = ScaleBias::new(plains_base).set_scale(0.0625).set_bias(0.0625);
+
+    // 6: бесплодные земли — ridged multifractal с примесью billow,
+    // деформированные турбулентностью на BADLANDS_TWIST.
+    let badlands_ridged = RidgedMulti::new()
+        .set_seed(seed.wrapping_add(BADLANDS_SEED_OFFSET))
+        .set_frequency(16111.0)
+        .set_lacunarity(params.badlands_lacunarity)
+        .set_octaves(1);
+
+    let badlands_billow = Billow::new()
+        .set_seed(seed.wrapping_add(BADLANDS_SEED_OFFSET + 1))
+        .set_frequency(16111.0)
+        .set_persistence(0.5)
+        .set_lacunarity(params.badlands_lacunarity)
+        .set_octaves(2);
+
+    let badlands_combined = Add::new(
+        ScaleBias::new(badlands_ridged).set_scale(0.5).set_bias(0.5),
+        ScaleBias::new(badlands_billow).set_scale(0.25).set_bias(0.0),
+    );
+
+    let badlands_warped = Turbulence::new(badlands_combined)
+        .set_seed(seed.wrapping_add(BADLANDS_SEED_OFFSET + 2))
+        .set_frequency(16111.0 * 2.0)
+        .set_power(params.badlands_twist / 16111.0)
+        .set_roughness(4);
+
+    let badlands = ScaleBias::new(badlands_warped).set_scale(0.5).set_bias(0.5);
+
+    // Низкочастотный шум "типа местности", управляющий тем, какой из четырёх
+    // рельефов проявляется в данной точке. Порядок наложения документирован
+    // в WorldParams: бесплодные земли перекрывают горы, горы — холмы, холмы —
+    // равнины.
+    let terrain_type = ScaleBias::new(Perlin::new().set_seed(seed.wrapping_add(TERRAIN_TYPE_SEED_OFFSET)))
+        .set_scale(0.5)
+        .set_bias(0.5);
+
+    let plains_or_hills = Select::new(plains, hills, terrain_type)
+        .set_bounds(1.0 - params.hills_amount, 1.0)
+        .set_falloff(params.terrain_offset);
+
+    let with_mountains = Select::new(plains_or_hills, mountains, terrain_type)
+        .set_bounds(1.0 - params.mountains_amount, 1.0)
+        .set_falloff(params.terrain_offset);
+
+    let terrain_blend = Select::new(with_mountains, badlands, terrain_type)
+        .set_bounds(1.0 - params.badlands_amount, 1.0)
+        .set_falloff(params.terrain_offset);
+
+    // Итог: форма континента плюс примешанный рельеф, масштабированные на
+    // CONTINENT_HEIGHT_SCALE.
+    let elevation = ScaleBias::new(Add::new(continent_def, terrain_blend))
+        .set_scale(params.continent_height_scale)
+        .set_bias(0.0);
+
+    Box::new(elevation)
+}
+
+#[cfg(test)]
+mod tests {
+    use noise::Constant;
+
+    use super::*;
+
+    #[test]
+    fn same_seed_and_params_reproduce_identical_elevation() {
+        let params = WorldParams::default();
+        let a = build_elevation(7, &params);
+        let b = build_elevation(7, &params);
+
+        for point in [[0.1, 0.2, 0.9], [-0.5, 0.3, 0.8], [0.0, 1.0, 0.0]] {
+            assert_eq!(a.get(point), b.get(point));
+        }
+    }
+
+    /// Воспроизводит ровно ту же цепочку `Select`, что и `build_elevation`,
+    /// но с константными "слоями"-метками вместо настоящего шума, чтобы
+    /// проверить саму границу вложенности порогов независимо от шума.
+    fn select_layer(
+        hills_amount: f64,
+        mountains_amount: f64,
+        badlands_amount: f64,
+        terrain_offset: f64,
+        control: f64,
+    ) -> f64 {
+        const PLAINS: f64 = 1.0;
+        const HILLS: f64 = 2.0;
+        const MOUNTAINS: f64 = 3.0;
+        const BADLANDS: f64 = 4.0;
+
+        let terrain_type = Constant::new(control);
+
+        let plains_or_hills = Select::new(Constant::new(PLAINS), Constant::new(HILLS), terrain_type)
+            .set_bounds(1.0 - hills_amount, 1.0)
+            .set_falloff(terrain_offset);
+
+        let with_mountains = Select::new(plains_or_hills, Constant::new(MOUNTAINS), terrain_type)
+            .set_bounds(1.0 - mountains_amount, 1.0)
+            .set_falloff(terrain_offset);
+
+        let with_badlands = Select::new(with_mountains, Constant::new(BADLANDS), terrain_type)
+            .set_bounds(1.0 - badlands_amount, 1.0)
+            .set_falloff(terrain_offset);
+
+        with_badlands.get([0.0, 0.0, 0.0])
+    }
+
+    #[test]
+    fn terrain_type_select_reaches_all_four_terrain_layers() {
+        let params = WorldParams::default();
+        // Узкий falloff, чтобы на границах не смешивались соседние слои.
+        let falloff = 0.001;
+        let mut seen = [false; 4];
+
+        for step in 0..=1000 {
+            let control = step as f64 / 1000.0;
+            let layer = select_layer(
+                params.hills_amount,
+                params.mountains_amount,
+                params.badlands_amount,
+                falloff,
+                control,
+            );
+
+            match layer.round() as i64 {
+                1 => seen[0] = true,
+                2 => seen[1] = true,
+                3 => seen[2] = true,
+                4 => seen[3] = true,
+                _ => {}
+            }
+        }
+
+        assert!(
+            seen.iter().all(|&reached| reached),
+            "ожидались все четыре типа местности (равнины/холмы/горы/бесплодные земли): {:?}",
+            seen
+        );
+    }
+
+    #[test]
+    fn terrain_type_select_reaches_all_four_layers_for_every_builtin_preset() {
+        // Регрессия на перекрытие слоёв: у "архипелага" badlands_amount когда-то
+        // оставался на значении по умолчанию и полностью затмевал горы.
+        let falloff = 0.001;
+
+        for params in [
+            WorldParams::default(),
+            WorldParams::archipelago(),
+            WorldParams::pangaea(),
+        ] {
+            let mut seen = [false; 4];
+
+            for step in 0..=1000 {
+                let control = step as f64 / 1000.0;
+                let layer = select_layer(
+                    params.hills_amount,
+                    params.mountains_amount,
+                    params.badlands_amount,
+                    falloff,
+                    control,
+                );
+
+                match layer.round() as i64 {
+                    1 => seen[0] = true,
+                    2 => seen[1] = true,
+                    3 => seen[2] = true,
+                    4 => seen[3] = true,
+                    _ => {}
+                }
+            }
+
+            assert!(
+                seen.iter().all(|&reached| reached),
+                "пресет {:?} не достигает все четыре типа местности: {:?}",
+                params,
+                seen
+            );
+        }
+    }
+
+    #[test]
+    fn shelf_clamp_carves_at_shelf_level() {
+        let params = WorldParams::default();
+        let below_shelf = Clamp::new(Constant::new(params.shelf_level - 0.2)).set_bounds(params.shelf_level, 1.0);
+
+        assert_eq!(below_shelf.get([0.0, 0.0, 0.0]), params.shelf_level);
+    }
+}
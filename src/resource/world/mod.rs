@@ -1,101 +1,63 @@
+mod constraints;
 mod continent;
+mod preset;
+mod river;
 
-use lazy_static::lazy_static;
+use std::path::Path;
+
+use noise::NoiseFn;
 use rand::Rng;
 
-lazy_static! {
-    /// Сид ключ для уникальной генерации планетарного ландшафта
-    pub static ref CURRENT_SEED: u32 = 0;
-
-    /// Частота континентов планеты. Более высокая частота производит
-    /// более мелкие и многочисленные континенты.
-    /// Значение измеряется в радианах.
-    pub(super) static ref CONTINENT_FREQUENCY: f64 = 1.0;
-
-    /// Лакунарность континентов планеты. Изменение этого значения приводит к
-    /// немного разные континенты. Для достижения наилучших результатов это значение должно
-    /// быть случайным, но близким к 2.0.
-    pub(super) static ref CONTINENT_LACUNARITY: f64 = 2.208984375;
-
-    /// Лакунарность гор планеты. Изменение значения производит
-    /// немного другие горы. Для достижения наилучших результатов это значение должно
-    /// быть случайным, но близким к 2.0.
-    pub(super) static ref MOUNTAIN_LACUNARITY: f64 = 2.142578125;
-
-    /// Лакунарность холмов планеты. Изменение этого значения приводит к
-    /// генерации других холмов. Для достижения наилучших результатов это значение должно быть
-    /// случайно, но близко к 2.0.
-    pub(super) static ref HILLS_LACUNARITY: f64 = 2.162109375;
-
-    /// Лакунарность равнин планеты. Изменение этого значения приводит к
-    /// генерации других равнин. Для достижения наилучших результатов это значение должно быть
-    /// случайно, но близко к 2.0.
-    pub(super) static ref PLAINS_LACUNARITY: f64 = 2.314453125;
-
-    /// Лакунарность бесплодных земель планеты. Изменение этого значения приводит к
-    /// генерации других бесплодных земль. Для достижения наилучших результатов это значение должно
-    /// быть случайным, но близким к 2.0.
-    pub(super) static ref BADLANDS_LACUNARITY: f64 = 2.212890625;
-
-    /// Определяет "извилистость" гор.
-    pub(super) static ref MOUNTAINS_TWIST: f64 = 1.0;
-
-    /// Определяет «извилистость» холмов.
-    pub(super) static ref HILLS_TWIST: f64 = 1.0;
-
-    /// Определяет «извилистость» бесплодных земель.
-    pub(super) static ref BADLANDS_TWIST: f64 = 1.0;
-
-    /// Определяет уровень моря на планете. Это значение должно быть между -1,0
-    /// (минимальная высота планеты) и +1.0 (максимальная высота планеты).
-    pub(super) static ref SEA_LEVEL: f64 = 0.0;
-
-    /// Указывает уровень на планете, на котором появляются континентальные шельфы.
-    /// Это значение должно быть между -1,0 (минимальная высота планеты) и +1,0
-    /// (максимальная высота планеты) и должно быть меньше `SEA_LEVEL`.
-    pub(super) static ref SHELF_LEVEL: f64 = -0.375;
-
-    /// Определяет количество гористой местности, которая появляется на
-    /// планета. Значения варьируются от 0,0 (горы отсутствуют) до 1,0 (вся местность
-    /// покрыто горами). Горный рельеф будет перекрывать холмистую местность.
-    /// Поскольку местность бесплодных земель может перекрывать части горной местности
-    /// местность, установка `MOUNTAINS_AMOUNT` на 1.0 может не полностью покрывать
-    /// местность в горах.
-    pub(super) static ref MOUNTAINS_AMOUNT: f64 = 0.48;
-
-    /// Определяет количество холмистой местности, которая появляется на планете.
-    /// Значения варьируются от 0,0 (холмы отсутствуют) до 1,0 (вся местность покрыта
-    /// холмы). Это значение должно быть меньше `MOUNTAINS_AMOUNT`. Поскольку
-    /// горный рельеф будет перекрывать части холмистой местности, а
-    /// ландшафт бесплодных земель может перекрывать части холмистой местности, устанавливая
-    /// `HILLS_AMOUNT` на 1.0 может не полностью покрывать холмистую местность.
-    pub(super) static ref HILLS_AMOUNT: f64 = (1.0 + *MOUNTAINS_AMOUNT) / 2.0;
-
-    /// Определяет количество бесплодных земель, покрывающих планету.
-    /// Значения варьируются от 0,0 (без бесплодных земель) до 1,0 (вся местность покрыта
-    /// бесплодные земли). Ландшафт бесплодных земель будет накладываться на любой другой тип ландшафта.
-    pub(super) static ref BADLANDS_AMOUNT: f64 = 0.3125;
-
-    /// Смещение для применения к определению типа ландшафта. Низкие значения (< 1,0)
-    /// заставляют шероховатые области появляться только на больших высотах. Высокие значения
-    /// (> 2.0) заставляют шероховатые области появляться на любой высоте.
-    /// процент грубых участков на планете не зависит от этого значения.
-    pub(super) static ref TERRAIN_OFFSET: f64 = 1.0;
-
-    /// Определяет количество "оледенения" в горах. Это значение
-    /// должен быть близок к 1,0 и больше 1,0.
-    pub(super) static ref MOUNTAIN_GLACIATION: f64 = 0.375;
-
-    /// Масштабирование для применения к высотам базового континента в планетарных
-    /// единицы высоты.
-    pub(super) static ref CONTINENT_HEIGHT_SCALE: f64 = (1.0 - *SEA_LEVEL) / 4.0;
-
-    /// Максимальная глубина рек в планетарных единицах высоты.
-    pub(super) static ref RIVER_DEPTH: f64 = 0.0234375;
-}
+pub use constraints::{GenerationError, WorldConstraints, WorldStats};
+pub use preset::{PresetError, WorldParams};
+
+/// Множитель, по которому из мастер-сида выводится сид рельефа
+/// (`terrain_seed`), если он не задан явно.
+const TERRAIN_SEED_DERIVE: u32 = 0x9E37_79B9;
+
+/// Слагаемое, добавляемое при выводе `terrain_seed`, чтобы мастер-сид 0 не
+/// схлопывал оба производных сида в 0 (умножение само по себе этого не
+/// гарантирует).
+const TERRAIN_SEED_OFFSET: u32 = 0x1234_5679;
+
+/// Множитель, по которому из мастер-сида выводится сид "фич" планеты
+/// (`feature_seed`, например рек), если он не задан явно.
+const FEATURE_SEED_DERIVE: u32 = 0x85EB_CA6B;
 
+/// Слагаемое, добавляемое при выводе `feature_seed`, см. [`TERRAIN_SEED_OFFSET`].
+const FEATURE_SEED_OFFSET: u32 = 0x9ABC_DEF1;
+
+#[derive(Clone)]
 pub struct WorldBuilder {
     current_seed: u32,
+
+    /// Отдельный сид для континентов, гор, холмов и прочего базового рельефа.
+    /// Если не задан явно через [`WorldBuilder::set_terrain_seed`], выводится
+    /// из `current_seed`.
+    terrain_seed: Option<u32>,
+
+    /// Отдельный сид для независимых от рельефа подсистем (например, рек),
+    /// позволяющий менять их расположение, сохраняя те же континенты.
+    /// Если не задан явно через [`WorldBuilder::set_feature_seed`], выводится
+    /// из `current_seed`.
+    feature_seed: Option<u32>,
+
+    /// Включает врезание рек в сушу. Включено по умолчанию.
+    rivers: bool,
+
+    params: WorldParams,
+}
+
+impl Default for WorldBuilder {
+    fn default() -> Self {
+        Self {
+            current_seed: 0,
+            terrain_seed: None,
+            feature_seed: None,
+            rivers: true,
+            params: WorldParams::default(),
+        }
+    }
 }
 
 impl WorldBuilder {
@@ -106,12 +68,427 @@ impl WorldBuilder {
 
         Self {
             current_seed: rng.gen::<u32>(),
+            ..Default::default()
         }
     }
 
+    /// Создаёт конструктор из уже готового набора параметров генерации,
+    /// например загруженного через [`WorldBuilder::from_preset`] или
+    /// полученного через [`WorldParams::archipelago`] / [`WorldParams::pangaea`].
+    pub fn with_params(mut self, params: WorldParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Загружает параметры генерации мира из текстового JSON файла,
+    /// сохранённого ранее через [`WorldBuilder::save_preset`].
+    pub fn from_preset<P: AsRef<Path>>(path: P) -> Result<Self, PresetError> {
+        Ok(Self::new().with_params(WorldParams::from_preset(path)?))
+    }
+
+    /// Сохраняет текущие параметры генерации мира в текстовый JSON файл,
+    /// чтобы поделиться рецептом мира или приложить его к отчёту об ошибке.
+    pub fn save_preset<P: AsRef<Path>>(&self, path: P) -> Result<(), PresetError> {
+        self.params.save_preset(path)
+    }
+
+    /// Встроенный пресет «архипелаг»: частые, мелкие континенты и спокойный рельеф.
+    pub fn archipelago() -> Self {
+        Self::new().with_params(WorldParams::archipelago())
+    }
+
+    /// Встроенный пресет «пангея»: один большой сверхконтинент.
+    pub fn pangaea() -> Self {
+        Self::new().with_params(WorldParams::pangaea())
+    }
+
     /// Функция позволяющая указать свой seed ключ для генерации мира.
     pub fn set_seed(mut self, seed: u32) -> Self {
         self.current_seed = seed;
         self
     }
-}
\ No newline at end of file
+
+    /// Задаёт отдельный сид для рельефа (континентов, гор, холмов, равнин и
+    /// бесплодных земель), независимый от мастер-сида.
+    pub fn set_terrain_seed(mut self, seed: u32) -> Self {
+        self.terrain_seed = Some(seed);
+        self
+    }
+
+    /// Задаёт отдельный сид для подсистем, не формирующих сам рельеф
+    /// (например, рек), позволяя менять их расположение, сохраняя те же
+    /// континенты.
+    pub fn set_feature_seed(mut self, seed: u32) -> Self {
+        self.feature_seed = Some(seed);
+        self
+    }
+
+    /// Включает или выключает врезание рек в сушу.
+    pub fn with_rivers(mut self, enabled: bool) -> Self {
+        self.rivers = enabled;
+        self
+    }
+
+    /// Задаёт частоту континентов планеты.
+    pub fn with_continent_frequency(mut self, value: f64) -> Self {
+        self.params.continent_frequency = value;
+        self
+    }
+
+    /// Задаёт лакунарность континентов планеты.
+    pub fn with_continent_lacunarity(mut self, value: f64) -> Self {
+        self.params.continent_lacunarity = value;
+        self
+    }
+
+    /// Задаёт лакунарность гор планеты.
+    pub fn with_mountain_lacunarity(mut self, value: f64) -> Self {
+        self.params.mountain_lacunarity = value;
+        self
+    }
+
+    /// Задаёт лакунарность холмов планеты.
+    pub fn with_hills_lacunarity(mut self, value: f64) -> Self {
+        self.params.hills_lacunarity = value;
+        self
+    }
+
+    /// Задаёт лакунарность равнин планеты.
+    pub fn with_plains_lacunarity(mut self, value: f64) -> Self {
+        self.params.plains_lacunarity = value;
+        self
+    }
+
+    /// Задаёт лакунарность бесплодных земель планеты.
+    pub fn with_badlands_lacunarity(mut self, value: f64) -> Self {
+        self.params.badlands_lacunarity = value;
+        self
+    }
+
+    /// Задаёт "извилистость" гор.
+    pub fn with_mountains_twist(mut self, value: f64) -> Self {
+        self.params.mountains_twist = value;
+        self
+    }
+
+    /// Задаёт «извилистость» холмов.
+    pub fn with_hills_twist(mut self, value: f64) -> Self {
+        self.params.hills_twist = value;
+        self
+    }
+
+    /// Задаёт «извилистость» бесплодных земель.
+    pub fn with_badlands_twist(mut self, value: f64) -> Self {
+        self.params.badlands_twist = value;
+        self
+    }
+
+    /// Задаёт уровень моря на планете.
+    pub fn with_sea_level(mut self, value: f64) -> Self {
+        self.params.sea_level = value;
+        self
+    }
+
+    /// Задаёт уровень на планете, на котором появляются континентальные шельфы.
+    pub fn with_shelf_level(mut self, value: f64) -> Self {
+        self.params.shelf_level = value;
+        self
+    }
+
+    /// Задаёт количество гористой местности, которая появляется на планете.
+    pub fn with_mountains_amount(mut self, value: f64) -> Self {
+        self.params.mountains_amount = value;
+        self
+    }
+
+    /// Задаёт количество холмистой местности, которая появляется на планете.
+    pub fn with_hills_amount(mut self, value: f64) -> Self {
+        self.params.hills_amount = value;
+        self
+    }
+
+    /// Задаёт количество бесплодных земель, покрывающих планету.
+    pub fn with_badlands_amount(mut self, value: f64) -> Self {
+        self.params.badlands_amount = value;
+        self
+    }
+
+    /// Задаёт смещение для применения к определению типа ландшафта.
+    pub fn with_terrain_offset(mut self, value: f64) -> Self {
+        self.params.terrain_offset = value;
+        self
+    }
+
+    /// Задаёт количество "оледенения" в горах.
+    pub fn with_mountain_glaciation(mut self, value: f64) -> Self {
+        self.params.mountain_glaciation = value;
+        self
+    }
+
+    /// Задаёт масштабирование для применения к высотам базового континента.
+    pub fn with_continent_height_scale(mut self, value: f64) -> Self {
+        self.params.continent_height_scale = value;
+        self
+    }
+
+    /// Задаёт максимальную глубину рек в планетарных единицах высоты.
+    pub fn with_river_depth(mut self, value: f64) -> Self {
+        self.params.river_depth = value;
+        self
+    }
+
+    /// Строит граф модулей шума и возвращает готовый к использованию мир.
+    pub fn build(self) -> World {
+        let terrain_seed = self.terrain_seed.unwrap_or_else(|| {
+            self.current_seed
+                .wrapping_mul(TERRAIN_SEED_DERIVE)
+                .wrapping_add(TERRAIN_SEED_OFFSET)
+        });
+        let feature_seed = self.feature_seed.unwrap_or_else(|| {
+            self.current_seed
+                .wrapping_mul(FEATURE_SEED_DERIVE)
+                .wrapping_add(FEATURE_SEED_OFFSET)
+        });
+
+        let river_position = if self.rivers {
+            Some(river::build_river_position(feature_seed))
+        } else {
+            None
+        };
+
+        World {
+            elevation: continent::build_elevation(terrain_seed, &self.params),
+            feature_seed,
+            sea_level: self.params.sea_level,
+            river_position,
+            river_depth: self.params.river_depth,
+            river_taper_distance: self.params.continent_height_scale * 0.5,
+        }
+    }
+
+    /// Генерирует мир, подбирая seed так, чтобы он удовлетворял заданным
+    /// [`WorldConstraints`], перегенерируя с новым seed при неудаче — по
+    /// образцу отбраковки миров в Dwarf Fortress. Возвращает принятый seed
+    /// вместе с миром.
+    pub fn generate_matching(
+        mut self,
+        constraints: &WorldConstraints,
+        max_attempts: u32,
+    ) -> Result<(u32, World), GenerationError> {
+        let mut rng = rand::thread_rng();
+        let mut stats = WorldStats {
+            land_fraction: 0.0,
+            mountainous_fraction: 0.0,
+            largest_landmass_fraction: 0.0,
+        };
+
+        for _ in 0..max_attempts {
+            let seed = self.current_seed;
+            let world = self.clone().build();
+            stats = constraints::measure(&world, constraints.resolution());
+
+            if constraints.is_satisfied_by(&stats) {
+                return Ok((seed, world));
+            }
+
+            // Сиды, явно закреплённые через `set_terrain_seed`/`set_feature_seed`,
+            // не трогаем — перегенерируем только мастер-сид и то, что из него
+            // выводится.
+            self.current_seed = rng.gen();
+        }
+
+        Err(GenerationError {
+            attempts: max_attempts,
+            stats,
+        })
+    }
+}
+
+/// Сгенерированный мир: неизменяемый источник высоты рельефа, построенный
+/// [`WorldBuilder::build`].
+pub struct World {
+    elevation: Box<dyn NoiseFn<[f64; 3]> + Send + Sync>,
+
+    /// Сид, используемый подсистемами, не формирующими сам рельеф
+    /// (например, реками), независимый от сида континентов.
+    feature_seed: u32,
+
+    sea_level: f64,
+
+    /// Шум положения рек. Отсутствует, если реки отключены через
+    /// [`WorldBuilder::with_rivers`].
+    river_position: Option<Box<dyn NoiseFn<[f64; 3]> + Send + Sync>>,
+
+    /// Максимальная глубина врезания рек, см. [`WorldParams`].
+    river_depth: f64,
+
+    /// Высота над уровнем моря, начиная с которой реки врезаются на полную
+    /// глубину; ниже неё врезание линейно убывает до нуля у уровня моря, чтобы
+    /// реки плавно впадали в море.
+    river_taper_distance: f64,
+}
+
+impl World {
+    /// Высота рельефа в точке с заданными широтой и долготой (в градусах).
+    /// Широта и долгота проецируются на единичную сферу, чтобы избежать швов
+    /// на полюсах и линии перемены даты.
+    pub fn elevation(&self, lat: f64, lon: f64) -> f64 {
+        let point = lat_lon_to_point(lat, lon);
+        let base = self.elevation.get(point);
+
+        let river_position = match &self.river_position {
+            Some(river_position) => river_position,
+            None => return base,
+        };
+
+        if base <= self.sea_level {
+            return base;
+        }
+
+        let channel = river_position.get(point).abs();
+        carve_river(
+            base,
+            self.sea_level,
+            channel,
+            self.river_depth,
+            self.river_taper_distance,
+        )
+    }
+}
+
+/// Врезает русло реки в высоту суши `base`. Вызывается только для точек
+/// выше уровня моря — океанское дно реки не трогают.
+///
+/// `channel` — абсолютное значение шума положения рек в данной точке;
+/// нулевые пересечения гребневого шума образуют ветвящиеся русла, так что
+/// чем ближе `channel` к нулю, тем ближе точка к руслу. `river_depth` —
+/// максимальная глубина врезания, `taper_distance` — высота над уровнем
+/// моря, начиная с которой врезание идёт на полную глубину; ниже неё оно
+/// линейно убывает до нуля у уровня моря, чтобы реки впадали в море, не
+/// прорезая океанское дно.
+fn carve_river(base: f64, sea_level: f64, channel: f64, river_depth: f64, taper_distance: f64) -> f64 {
+    let trench = (1.0 - channel).max(0.0) * river_depth;
+    let taper = ((base - sea_level) / taper_distance).min(1.0);
+
+    base - trench * taper
+}
+
+/// Переводит широту/долготу в градусах в точку на единичной сфере.
+fn lat_lon_to_point(lat: f64, lon: f64) -> [f64; 3] {
+    let lat = lat.to_radians();
+    let lon = lon.to_radians();
+    let r = lat.cos();
+
+    [r * lon.cos(), lat.sin(), r * lon.sin()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_COORDS: [(f64, f64); 4] =
+        [(10.0, 20.0), (-40.0, 170.0), (0.0, 0.0), (55.0, -100.0)];
+
+    #[test]
+    fn same_master_seed_reproduces_identical_world() {
+        let world_a = WorldBuilder::new().set_seed(123).build();
+        let world_b = WorldBuilder::new().set_seed(123).build();
+
+        for (lat, lon) in SAMPLE_COORDS {
+            assert_eq!(world_a.elevation(lat, lon), world_b.elevation(lat, lon));
+        }
+    }
+
+    #[test]
+    fn pinned_terrain_seed_decorrelates_from_master_seed() {
+        let world_a = WorldBuilder::new().set_seed(1).set_terrain_seed(999).with_rivers(false).build();
+        let world_b = WorldBuilder::new().set_seed(2).set_terrain_seed(999).with_rivers(false).build();
+
+        // Рельеф зависит только от terrain_seed, так что форма континентов
+        // должна совпадать даже при разных мастер-сидах. Реки отключены: их
+        // сид выводится из мастер-сида и врезание у этих миров различается.
+        for (lat, lon) in SAMPLE_COORDS {
+            assert_eq!(world_a.elevation(lat, lon), world_b.elevation(lat, lon));
+        }
+    }
+
+    #[test]
+    fn differing_feature_seed_changes_rivers_but_not_terrain_shape() {
+        let base = WorldBuilder::new().set_terrain_seed(42).set_feature_seed(1).build();
+        let varied = WorldBuilder::new().set_terrain_seed(42).set_feature_seed(2).build();
+
+        let mut any_difference = false;
+        for step in 0..200 {
+            let lat = -80.0 + step as f64 * 0.7;
+            let lon = -170.0 + step as f64 * 1.3;
+
+            if (base.elevation(lat, lon) - varied.elevation(lat, lon)).abs() > 1e-9 {
+                any_difference = true;
+                break;
+            }
+        }
+
+        assert!(
+            any_difference,
+            "feature_seed должен влиять на врезание рек хотя бы в одной из сэмплированных точек"
+        );
+
+        // Без рек те же два мира совпадают: форма рельефа от feature_seed не зависит.
+        let base_terrain = WorldBuilder::new().set_terrain_seed(42).set_feature_seed(1).with_rivers(false).build();
+        let varied_terrain = WorldBuilder::new().set_terrain_seed(42).set_feature_seed(2).with_rivers(false).build();
+        for (lat, lon) in SAMPLE_COORDS {
+            assert_eq!(base_terrain.elevation(lat, lon), varied_terrain.elevation(lat, lon));
+        }
+    }
+
+    #[test]
+    fn zero_master_seed_still_decorrelates_terrain_and_feature_seeds() {
+        // При current_seed == 0 умножение само по себе схлопнуло бы и
+        // terrain_seed, и feature_seed в 0, заставив рельеф и реки совпадать
+        // с миром, явно закреплённым на обоих нулевых сидах.
+        let zero_master = WorldBuilder::new().set_seed(0).build();
+        let pinned_zero = WorldBuilder::new().set_terrain_seed(0).set_feature_seed(0).build();
+
+        let mut any_difference = false;
+        for (lat, lon) in SAMPLE_COORDS {
+            if (zero_master.elevation(lat, lon) - pinned_zero.elevation(lat, lon)).abs() > 1e-9 {
+                any_difference = true;
+                break;
+            }
+        }
+
+        assert!(
+            any_difference,
+            "мастер-сид 0 не должен выводить те же terrain/feature сиды, что и явные нули"
+        );
+    }
+
+    #[test]
+    fn carve_river_is_full_depth_on_the_channel_centerline() {
+        let carved = carve_river(0.5, 0.0, 0.0, 0.1, 0.5);
+        assert_eq!(carved, 0.5 - 0.1);
+    }
+
+    #[test]
+    fn carve_river_leaves_the_bank_untouched_away_from_the_channel() {
+        let carved = carve_river(0.5, 0.0, 1.0, 0.1, 1.0);
+        assert_eq!(carved, 0.5);
+    }
+
+    #[test]
+    fn carve_river_never_exceeds_river_depth() {
+        for channel in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let carved = carve_river(10.0, 0.0, channel, 0.1, 1.0);
+            assert!(10.0 - carved <= 0.1 + f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn carve_river_tapers_to_nothing_near_the_coastline() {
+        let carved = carve_river(0.001, 0.0, 0.0, 0.1, 1.0);
+        assert!(
+            (0.001 - carved).abs() < 1e-3,
+            "врезание у самого берега должно быть почти нулевым, получили {carved}"
+        );
+    }
+}
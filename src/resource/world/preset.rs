@@ -0,0 +1,234 @@
+//! Параметры генерации мира, пригодные для сохранения и повторного
+//! использования в виде текстового файла — аналог `world_gen.txt` в Dwarf
+//! Fortress или `worldgen_settings.json` в Minecraft.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Набор параметров генерации рельефа планеты, независимый от seed ключа.
+/// Хранится отдельно от [`super::WorldBuilder`], чтобы его можно было
+/// сериализовать, сохранить в файл и переиспользовать как готовый рецепт мира.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldParams {
+    /// Частота континентов планеты. Более высокая частота производит
+    /// более мелкие и многочисленные континенты.
+    /// Значение измеряется в радианах.
+    pub(super) continent_frequency: f64,
+
+    /// Лакунарность континентов планеты. Изменение этого значения приводит к
+    /// немного разные континенты. Для достижения наилучших результатов это значение должно
+    /// быть случайным, но близким к 2.0.
+    pub(super) continent_lacunarity: f64,
+
+    /// Лакунарность гор планеты. Изменение значения производит
+    /// немного другие горы. Для достижения наилучших результатов это значение должно
+    /// быть случайным, но близким к 2.0.
+    pub(super) mountain_lacunarity: f64,
+
+    /// Лакунарность холмов планеты. Изменение этого значения приводит к
+    /// генерации других холмов. Для достижения наилучших результатов это значение должно быть
+    /// случайно, но близко к 2.0.
+    pub(super) hills_lacunarity: f64,
+
+    /// Лакунарность равнин планеты. Изменение этого значения приводит к
+    /// генерации других равнин. Для достижения наилучших результатов это значение должно быть
+    /// случайно, но близко к 2.0.
+    pub(super) plains_lacunarity: f64,
+
+    /// Лакунарность бесплодных земель планеты. Изменение этого значения приводит к
+    /// генерации других бесплодных земль. Для достижения наилучших результатов это значение должно
+    /// быть случайным, но близким к 2.0.
+    pub(super) badlands_lacunarity: f64,
+
+    /// Определяет "извилистость" гор.
+    pub(super) mountains_twist: f64,
+
+    /// Определяет «извилистость» холмов.
+    pub(super) hills_twist: f64,
+
+    /// Определяет «извилистость» бесплодных земель.
+    pub(super) badlands_twist: f64,
+
+    /// Определяет уровень моря на планете. Это значение должно быть между -1,0
+    /// (минимальная высота планеты) и +1.0 (максимальная высота планеты).
+    pub(super) sea_level: f64,
+
+    /// Указывает уровень на планете, на котором появляются континентальные шельфы.
+    /// Это значение должно быть между -1,0 (минимальная высота планеты) и +1,0
+    /// (максимальная высота планеты) и должно быть меньше `sea_level`.
+    pub(super) shelf_level: f64,
+
+    /// Определяет количество гористой местности, которая появляется на
+    /// планета. Значения варьируются от 0,0 (горы отсутствуют) до 1,0 (вся местность
+    /// покрыто горами). Горный рельеф будет перекрывать холмистую местность.
+    /// Поскольку местность бесплодных земель может перекрывать части горной местности
+    /// местность, установка `mountains_amount` на 1.0 может не полностью покрывать
+    /// местность в горах.
+    pub(super) mountains_amount: f64,
+
+    /// Определяет количество холмистой местности, которая появляется на планете.
+    /// Значения варьируются от 0,0 (холмы отсутствуют) до 1,0 (вся местность покрыта
+    /// холмы). Это значение должно быть больше `mountains_amount`. Поскольку
+    /// горный рельеф будет перекрывать части холмистой местности, а
+    /// ландшафт бесплодных земель может перекрывать части холмистой местности, устанавливая
+    /// `hills_amount` на 1.0 может не полностью покрывать холмистую местность.
+    pub(super) hills_amount: f64,
+
+    /// Определяет количество бесплодных земель, покрывающих планету.
+    /// Значения варьируются от 0,0 (без бесплодных земель) до 1,0 (вся местность покрыта
+    /// бесплодные земли). Ландшафт бесплодных земель будет накладываться на любой другой тип ландшафта.
+    pub(super) badlands_amount: f64,
+
+    /// Ширина плавного перехода (falloff) на границах между типами ландшафта
+    /// при их выборе по шуму типа местности. От высоты рельефа не зависит.
+    pub(super) terrain_offset: f64,
+
+    /// Определяет количество "оледенения" в горах: высота гор масштабируется
+    /// на (1,0 - значение) и поднимается на значение, так что долины становятся
+    /// мельче, а пики остаются на месте. Значение должно быть между 0,0 и 1,0.
+    pub(super) mountain_glaciation: f64,
+
+    /// Масштабирование для применения к высотам базового континента в планетарных
+    /// единицы высоты.
+    pub(super) continent_height_scale: f64,
+
+    /// Максимальная глубина рек в планетарных единицах высоты.
+    pub(super) river_depth: f64,
+}
+
+impl Default for WorldParams {
+    fn default() -> Self {
+        let sea_level = 0.0;
+        let mountains_amount = 0.48;
+
+        Self {
+            continent_frequency: 1.0,
+            continent_lacunarity: 2.208984375,
+            mountain_lacunarity: 2.142578125,
+            hills_lacunarity: 2.162109375,
+            plains_lacunarity: 2.314453125,
+            badlands_lacunarity: 2.212890625,
+            mountains_twist: 1.0,
+            hills_twist: 1.0,
+            badlands_twist: 1.0,
+            sea_level,
+            shelf_level: -0.375,
+            mountains_amount,
+            hills_amount: (1.0 + mountains_amount) / 2.0,
+            badlands_amount: 0.3125,
+            terrain_offset: 1.0,
+            mountain_glaciation: 0.375,
+            continent_height_scale: (1.0 - sea_level) / 4.0,
+            river_depth: 0.0234375,
+        }
+    }
+}
+
+impl WorldParams {
+    /// Читает набор параметров из текстового JSON файла.
+    pub fn from_preset<P: AsRef<Path>>(path: P) -> Result<Self, PresetError> {
+        let raw = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&raw)?)
+    }
+
+    /// Сохраняет набор параметров в текстовый JSON файл.
+    pub fn save_preset<P: AsRef<Path>>(&self, path: P) -> Result<(), PresetError> {
+        let raw = serde_json::to_string_pretty(self)?;
+        fs::write(path, raw)?;
+        Ok(())
+    }
+
+    /// Архипелаг: частые, мелкие континенты и спокойный рельеф.
+    /// Аналог готовых рецептов мира из Dwarf Fortress.
+    pub fn archipelago() -> Self {
+        Self {
+            continent_frequency: 2.2,
+            mountains_amount: 0.2,
+            hills_amount: 0.45,
+            // Должно оставаться меньше mountains_amount, иначе полоса
+            // бесплодных земель полностью перекрывает горы (см. порядок
+            // наложения в наложении Select-слоёв в continent.rs).
+            badlands_amount: 0.1,
+            ..Default::default()
+        }
+    }
+
+    /// Пангея: один большой сверхконтинент с выраженными горными поясами.
+    pub fn pangaea() -> Self {
+        Self {
+            continent_frequency: 0.45,
+            mountains_amount: 0.6,
+            hills_amount: 0.8,
+            ..Default::default()
+        }
+    }
+}
+
+/// Ошибка чтения или записи пресета параметров генерации мира.
+#[derive(Debug)]
+pub enum PresetError {
+    Io(io::Error),
+    Serde(serde_json::Error),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PresetError::Io(err) => write!(f, "ошибка чтения пресета: {}", err),
+            PresetError::Serde(err) => write!(f, "ошибка разбора пресета: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PresetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PresetError::Io(err) => Some(err),
+            PresetError::Serde(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for PresetError {
+    fn from(err: io::Error) -> Self {
+        PresetError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(err: serde_json::Error) -> Self {
+        PresetError::Serde(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn preset_round_trips_through_json() {
+        let path = env::temp_dir().join("wofc-preset-round-trip-test.json");
+
+        let original = WorldParams::archipelago();
+        original.save_preset(&path).expect("save preset");
+        let loaded = WorldParams::from_preset(&path).expect("load preset");
+
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(original, loaded);
+    }
+
+    #[test]
+    fn from_preset_reports_an_error_for_a_missing_file() {
+        let path = env::temp_dir().join("wofc-preset-does-not-exist.json");
+        let _ = std::fs::remove_file(&path);
+
+        assert!(WorldParams::from_preset(&path).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+//! Генерация русел рек.
+//!
+//! Реки выводятся не как отдельный слой высоты, а как "позиционный" шум:
+//! гребневый мультифрактал, у которого линии нулевого пересечения образуют
+//! ветвящиеся русла долин. [`super::World::elevation`] врезает по этим
+//! руслам неглубокую траншею в сушу, не затрагивая океанское дно.
+
+use noise::{MultiFractal, NoiseFn, RidgedMulti, Seedable};
+
+const RIVER_POSITION_SEED_OFFSET: u32 = 0;
+
+/// Строит шум положения рек по независимому сиду "фич" мира.
+pub(super) fn build_river_position(seed: u32) -> Box<dyn NoiseFn<[f64; 3]> + Send + Sync> {
+    Box::new(
+        RidgedMulti::new()
+            .set_seed(seed.wrapping_add(RIVER_POSITION_SEED_OFFSET))
+            .set_frequency(1063.0)
+            .set_lacunarity(2.125)
+            .set_octaves(1),
+    )
+}